@@ -0,0 +1,113 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/datagram-chunker
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+use criterion::{criterion_group, criterion_main, Criterion};
+use datagram_chunker::{serialize_to_datagrams, SizeHint};
+use flood_rs::{Deserialize, Serialize};
+use std::hint::black_box;
+use std::io;
+
+#[derive(Debug)]
+struct BenchMessage {
+    id: u32,
+    content: String,
+}
+
+impl Serialize for BenchMessage {
+    fn serialize(&self, stream: &mut impl flood_rs::WriteOctetStream) -> io::Result<()> {
+        stream.write_u32(self.id)?;
+        let octets = self.content.as_bytes();
+        stream.write_u16(octets.len() as u16)?;
+        stream.write(octets)
+    }
+}
+
+impl Deserialize for BenchMessage {
+    fn deserialize(stream: &mut impl flood_rs::ReadOctetStream) -> io::Result<Self> {
+        let id = stream.read_u32()?;
+        let length = stream.read_u16()? as usize;
+        let mut buf = vec![0u8; length];
+        stream.read(&mut buf)?;
+        Ok(Self {
+            id,
+            content: String::from_utf8(buf)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "wrong"))?,
+        })
+    }
+}
+
+impl SizeHint for BenchMessage {
+    fn serialized_size_hint(&self) -> Option<usize> {
+        Some(4 + 2 + self.content.len())
+    }
+}
+
+/// Same wire layout as `BenchMessage`, but without a `serialized_size_hint`
+/// override, so `serialize_to_datagrams` falls back to the unhinted
+/// temp-buffer-and-copy path. Benchmarked alongside `BenchMessage` to show what the
+/// size hint's direct-into-`current` fast path actually saves.
+#[derive(Debug)]
+struct UnhintedBenchMessage {
+    id: u32,
+    content: String,
+}
+
+impl Serialize for UnhintedBenchMessage {
+    fn serialize(&self, stream: &mut impl flood_rs::WriteOctetStream) -> io::Result<()> {
+        stream.write_u32(self.id)?;
+        let octets = self.content.as_bytes();
+        stream.write_u16(octets.len() as u16)?;
+        stream.write(octets)
+    }
+}
+
+impl Deserialize for UnhintedBenchMessage {
+    fn deserialize(stream: &mut impl flood_rs::ReadOctetStream) -> io::Result<Self> {
+        let id = stream.read_u32()?;
+        let length = stream.read_u16()? as usize;
+        let mut buf = vec![0u8; length];
+        stream.read(&mut buf)?;
+        Ok(Self {
+            id,
+            content: String::from_utf8(buf)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "wrong"))?,
+        })
+    }
+}
+
+impl SizeHint for UnhintedBenchMessage {}
+
+fn messages(count: usize) -> Vec<BenchMessage> {
+    (0..count)
+        .map(|i| BenchMessage {
+            id: i as u32,
+            content: "x".repeat(32),
+        })
+        .collect()
+}
+
+fn unhinted_messages(count: usize) -> Vec<UnhintedBenchMessage> {
+    (0..count)
+        .map(|i| UnhintedBenchMessage {
+            id: i as u32,
+            content: "x".repeat(32),
+        })
+        .collect()
+}
+
+fn bench_serialize_to_datagrams(c: &mut Criterion) {
+    let hinted = messages(10_000);
+    let unhinted = unhinted_messages(10_000);
+
+    c.bench_function("serialize_to_datagrams hinted, 10k messages", |b| {
+        b.iter(|| serialize_to_datagrams(black_box(&hinted), black_box(1200)).unwrap())
+    });
+
+    c.bench_function("serialize_to_datagrams unhinted, 10k messages", |b| {
+        b.iter(|| serialize_to_datagrams(black_box(&unhinted), black_box(1200)).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_serialize_to_datagrams);
+criterion_main!(benches);
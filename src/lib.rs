@@ -11,19 +11,40 @@
 use err_rs::{ErrorLevel, ErrorLevelProvider};
 use flood_rs::in_stream::InOctetStream;
 use flood_rs::prelude::OutOctetStream;
-use flood_rs::{Deserialize, ReadOctetStream, Serialize};
+use flood_rs::{Deserialize, ReadOctetStream, Serialize, WriteOctetStream};
+use std::collections::HashSet;
 use std::fmt::{Debug, Display};
+use std::marker::PhantomData;
+use std::ops::Range;
 use std::{io, mem};
 
 pub mod prelude;
 
+/// An optional hint about the serialized size of a message, in octets.
+///
+/// [`serialize_to_datagrams`] uses this to decide, before serializing, whether a
+/// message is likely to fit in the current datagram, so it can flush early instead
+/// of serializing into a throwaway buffer only to discover it doesn't fit. Types
+/// that can't cheaply predict their size can implement this trait with its default
+/// body (`impl SizeHint for MyType {}`), which falls back to the unhinted path.
+pub trait SizeHint {
+    /// Returns the expected serialized size of `self`, if known in advance.
+    fn serialized_size_hint(&self) -> Option<usize> {
+        None
+    }
+}
+
 /// Represents errors that can occur while chunking datagrams.
 #[derive(Debug)]
 pub enum DatagramChunkerError {
-    /// The size of the item exceeds the maximum allowed datagram size.
+    /// The size of the item exceeds the maximum allowed datagram size, or (in framed
+    /// mode) its serialized body exceeds the `u16::MAX` length a frame can prefix it
+    /// with.
     ItemSizeTooBig,
     /// An I/O error occurred.
     IoError(io::Error),
+    /// A datagram declared a format version byte this build doesn't understand.
+    UnsupportedFormat(u8),
 }
 
 impl ErrorLevelProvider for DatagramChunkerError {
@@ -31,29 +52,227 @@ impl ErrorLevelProvider for DatagramChunkerError {
         match self {
             DatagramChunkerError::ItemSizeTooBig => ErrorLevel::Critical,
             DatagramChunkerError::IoError(_) => ErrorLevel::Info,
+            DatagramChunkerError::UnsupportedFormat(_) => ErrorLevel::Critical,
         }
     }
 }
 
+/// Identifies the wire layout of a datagram, carried as a one octet version prefix so
+/// a peer can reject a datagram written in a layout it doesn't understand instead of
+/// misparsing it. [`DatagramChunker::new_framed`] writes this prefix too, using
+/// `V2` - it is the same wire layout as [`DatagramChunker::new_with_format`] with
+/// `V2`, so [`deserialize_datagram_framed`] and [`deserialize_datagram_versioned`]
+/// agree on what version byte `2` means instead of running disjoint numbering
+/// schemes that happen to share a byte value.
+///
+/// `V1` is the original raw concatenation of serialized messages, with no length
+/// prefixes. `V2` adds the length-prefixed, message-counted framing, letting a
+/// corrupt message be isolated instead of desynchronizing the rest of the datagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatagramFormat {
+    V1,
+    V2,
+}
+
+impl DatagramFormat {
+    fn version_byte(self) -> u8 {
+        match self {
+            DatagramFormat::V1 => 1,
+            DatagramFormat::V2 => 2,
+        }
+    }
+
+    fn from_version_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(DatagramFormat::V1),
+            2 => Some(DatagramFormat::V2),
+            _ => None,
+        }
+    }
+}
+
+/// Number of octets used for the `u16` message count at the start of a framed datagram.
+const FRAME_COUNT_SIZE: usize = 2;
+
+/// Number of octets used to prefix each message body in framed mode.
+const FRAME_LENGTH_PREFIX_SIZE: usize = 2;
+
+/// Number of header octets a channel tag adds to every produced datagram.
+const TAG_HEADER_SIZE: usize = 2;
+
+/// Identifies a single datagram produced by a [`DatagramChunker`]'s tracked finalize,
+/// in the order it was produced.
+///
+/// Pair this with [`DatagramChunker::finalize_tracked`] and [`rechunk_unacked`] to
+/// build reliable delivery on top of an unreliable transport: remember which
+/// `DatagramId`s the peer has acknowledged, and re-send the messages behind the ones
+/// that haven't been.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DatagramId(u64);
+
+impl DatagramId {
+    /// Returns the raw numeric value of this id.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Which wire layout a [`DatagramChunker`] was configured to produce, captured by
+/// [`DatagramChunker::finalize_tracked`] so [`rechunk_unacked`] can recreate an
+/// equivalent chunker for a retransmit instead of defaulting to
+/// [`DatagramChunker::new`]'s plain, unframed layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ChunkerMode {
+    framed: bool,
+    tag: Option<u16>,
+    format: Option<DatagramFormat>,
+}
+
+/// Maps each produced datagram's [`DatagramId`] to the range of input message
+/// indices it carried, as returned by [`DatagramChunker::finalize_tracked`].
+///
+/// Also carries the originating chunker's wire layout, so [`rechunk_unacked`] can
+/// re-create an equivalent chunker for the messages it resends. Derefs to the
+/// underlying `Vec<(DatagramId, Range<usize>)>` for inspection.
+#[derive(Debug, Clone)]
+pub struct DatagramTracking {
+    entries: Vec<(DatagramId, Range<usize>)>,
+    mode: ChunkerMode,
+}
+
+impl std::ops::Deref for DatagramTracking {
+    type Target = Vec<(DatagramId, Range<usize>)>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+/// A [`WriteOctetStream`] that writes straight into a caller-owned `Vec<u8>`, instead
+/// of the fresh buffer [`OutOctetStream`] allocates. Used to serialize a message
+/// directly into a [`DatagramChunker`]'s current datagram, without the
+/// serialize-into-temp-then-copy round trip.
+struct VecOctetWriter<'a> {
+    buf: &'a mut Vec<u8>,
+}
+
+impl<'a> VecOctetWriter<'a> {
+    fn new(buf: &'a mut Vec<u8>) -> Self {
+        Self { buf }
+    }
+}
+
+impl<'a> WriteOctetStream for VecOctetWriter<'a> {
+    fn write_u8(&mut self, v: u8) -> io::Result<()> {
+        self.buf.push(v);
+        Ok(())
+    }
+
+    fn write_u16(&mut self, v: u16) -> io::Result<()> {
+        self.buf.extend_from_slice(&v.to_be_bytes());
+        Ok(())
+    }
+
+    fn write_u32(&mut self, v: u32) -> io::Result<()> {
+        self.buf.extend_from_slice(&v.to_be_bytes());
+        Ok(())
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.buf.extend_from_slice(buf);
+        Ok(())
+    }
+}
+
 /// A utility for chunking messages into datagrams with a specified maximum size.
+///
+/// By default (see [`DatagramChunker::new`]) datagrams are produced by simply
+/// concatenating the serialized messages, matching [`serialize_to_datagrams`].
+/// [`DatagramChunker::new_framed`] instead produces self-describing datagrams
+/// (see [`serialize_to_datagrams_framed`]) so that a corrupt or truncated message
+/// can be detected and skipped instead of desynchronizing every message after it.
+/// [`DatagramChunker::new_with_tag`] additionally prefixes every produced datagram
+/// with a `u16` channel tag, so several logical message streams can share one
+/// transport (see [`serialize_to_datagrams_tagged`]).
 pub struct DatagramChunker {
     datagrams: Vec<Vec<u8>>,
     current: Vec<u8>,
+    current_message_count: u16,
     max_size: usize,
+    framed: bool,
+    tag: Option<u16>,
+    format: Option<DatagramFormat>,
+    message_index: usize,
+    current_start_index: usize,
+    ranges: Vec<Range<usize>>,
 }
 
 impl DatagramChunker {
+    fn with_options(max_size: usize, framed: bool, tag: Option<u16>, format: Option<DatagramFormat>) -> Self {
+        Self {
+            current: Vec::with_capacity(max_size),
+            current_message_count: 0,
+            datagrams: Vec::new(),
+            max_size,
+            framed,
+            tag,
+            format,
+            message_index: 0,
+            current_start_index: 0,
+            ranges: Vec::new(),
+        }
+    }
+
     /// Creates a new `DatagramChunker` with the given maximum datagram size.
     ///
     /// # Arguments
     ///
     /// * `max_size` - The maximum size of each datagram in bytes.
     pub fn new(max_size: usize) -> Self {
-        Self {
-            current: Vec::with_capacity(max_size),
-            datagrams: Vec::new(),
-            max_size,
-        }
+        Self::with_options(max_size, false, None, None)
+    }
+
+    /// Creates a new `DatagramChunker` that produces self-describing, framed datagrams.
+    ///
+    /// Each produced datagram starts with a [`DatagramFormat::V2`] version byte and a
+    /// `u16` message count, and every message body is preceded by a `u16` length
+    /// prefix. This makes it possible to isolate a single malformed message on
+    /// deserialization instead of corrupting the rest of the datagram.
+    ///
+    /// This is the same wire layout as `new_with_format(max_size, DatagramFormat::V2)`;
+    /// the result can be read back with either [`deserialize_datagram_framed`] or
+    /// [`deserialize_datagram_versioned`].
+    ///
+    /// # Arguments
+    ///
+    /// * `max_size` - The maximum size of each datagram in bytes, header included.
+    pub fn new_framed(max_size: usize) -> Self {
+        Self::with_options(max_size, true, None, Some(DatagramFormat::V2))
+    }
+
+    /// Creates a new `DatagramChunker` that prefixes every produced datagram with a
+    /// `u16` channel tag, letting a receiver route several logical message streams
+    /// (e.g. control vs. bulk traffic) that share a single transport.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_size` - The maximum size of each datagram in bytes, tag included.
+    /// * `tag` - The channel tag to write into the header of every produced datagram.
+    pub fn new_with_tag(max_size: usize, tag: u16) -> Self {
+        Self::with_options(max_size, false, Some(tag), None)
+    }
+
+    /// Creates a new `DatagramChunker` that prefixes every produced datagram with an
+    /// explicit [`DatagramFormat`] version byte, so a peer running a different build
+    /// can reject a datagram it doesn't know how to parse (see
+    /// [`deserialize_datagram_versioned`]) instead of misreading it.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_size` - The maximum size of each datagram in bytes, version byte included.
+    /// * `format` - The wire layout to write datagrams in.
+    pub fn new_with_format(max_size: usize, format: DatagramFormat) -> Self {
+        Self::with_options(max_size, format == DatagramFormat::V2, None, Some(format))
     }
 
     /// Pushes a message into the chunker, creating a new datagram if necessary.
@@ -67,26 +286,258 @@ impl DatagramChunker {
     /// Returns `DatagramChunkerError::ItemSizeTooBig` if the data size exceeds `max_size`.
     /// Propagates `DatagramChunkerError::IoError` if serialization fails.
     pub fn push(&mut self, buf: &[u8]) -> Result<(), DatagramChunkerError> {
-        if buf.len() > self.max_size {
+        if self.framed {
+            self.push_framed(buf)
+        } else {
+            self.push_unframed(buf)
+        }
+    }
+
+    /// Serializes `message` into the chunker using a size hint, avoiding the
+    /// throwaway buffer of [`DatagramChunker::push`]: the message is serialized
+    /// straight into `current` instead of into a temporary stream that then has to be
+    /// copied in.
+    ///
+    /// `hint` is used only to decide ahead of time whether the current datagram
+    /// should be flushed first; the actual serialized size is still checked
+    /// afterwards; if the hint undershot and the message doesn't fit, the message is
+    /// rolled back, the current datagram is flushed, and it's retried on a fresh one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DatagramChunkerError::ItemSizeTooBig` if the serialized message
+    /// exceeds `max_size` even on its own. Propagates `DatagramChunkerError::IoError`
+    /// if serialization fails.
+    pub fn push_hinted<T: Serialize>(
+        &mut self,
+        message: &T,
+        hint: usize,
+    ) -> Result<(), DatagramChunkerError> {
+        let capacity = self.capacity();
+        let reserved = if self.framed { FRAME_LENGTH_PREFIX_SIZE } else { 0 };
+
+        // A framed datagram's message count is a `u16`; flush before writing a
+        // 65536th message into `current` so `current_message_count += 1` below never
+        // wraps.
+        let count_would_overflow = self.framed && self.current_message_count == u16::MAX;
+        if count_would_overflow
+            || (!self.current.is_empty() && self.current.len() + reserved + hint > capacity)
+        {
+            self.flush_current();
+        }
+
+        let before_len = self.current.len();
+        let before_count = self.current_message_count;
+        self.write_message_into_current(message)?;
+
+        if self.current.len() > capacity {
+            self.current.truncate(before_len);
+            self.current_message_count = before_count;
+
+            if before_len == 0 {
+                return Err(DatagramChunkerError::ItemSizeTooBig);
+            }
+
+            self.flush_current();
+            self.write_message_into_current(message)?;
+
+            if self.current.len() > capacity {
+                self.current.truncate(0);
+                self.current_message_count = 0;
+                return Err(DatagramChunkerError::ItemSizeTooBig);
+            }
+        }
+
+        self.message_index += 1;
+        Ok(())
+    }
+
+    /// Serializes `message` directly into `self.current`, with no intermediate
+    /// buffer. In framed mode, a placeholder `u16` length is written first and
+    /// patched with the actual serialized size afterwards, since that size isn't
+    /// known until the message has been written.
+    ///
+    /// If serialization fails partway through, `self.current` and
+    /// `current_message_count` are rolled back to their state before this call, so a
+    /// failed `push_hinted` can't leave orphaned bytes for a later, successful push
+    /// to get spliced onto.
+    fn write_message_into_current<T: Serialize>(
+        &mut self,
+        message: &T,
+    ) -> Result<(), DatagramChunkerError> {
+        let before_len = self.current.len();
+        let before_count = self.current_message_count;
+
+        let result = self.write_message_into_current_unchecked(message);
+        if result.is_err() {
+            self.current.truncate(before_len);
+            self.current_message_count = before_count;
+        }
+
+        result
+    }
+
+    fn write_message_into_current_unchecked<T: Serialize>(
+        &mut self,
+        message: &T,
+    ) -> Result<(), DatagramChunkerError> {
+        if self.framed {
+            let prefix_index = self.current.len();
+            self.current.extend_from_slice(&[0u8, 0u8]);
+
+            let body_start = self.current.len();
+            message.serialize(&mut VecOctetWriter::new(&mut self.current))?;
+            let body_len = self.current.len() - body_start;
+
+            if body_len > u16::MAX as usize {
+                return Err(DatagramChunkerError::ItemSizeTooBig);
+            }
+
+            self.current[prefix_index..prefix_index + FRAME_LENGTH_PREFIX_SIZE]
+                .copy_from_slice(&(body_len as u16).to_be_bytes());
+            self.current_message_count += 1;
+        } else {
+            message.serialize(&mut VecOctetWriter::new(&mut self.current))?;
+        }
+
+        Ok(())
+    }
+
+    fn tag_overhead(&self) -> usize {
+        if self.tag.is_some() {
+            TAG_HEADER_SIZE
+        } else {
+            0
+        }
+    }
+
+    /// Whether a one octet [`DatagramFormat`] version is written in front of every
+    /// datagram. [`DatagramChunker::new_framed`] sets `format` to
+    /// [`DatagramFormat::V2`] just like [`DatagramChunker::new_with_format`] does, so
+    /// there's a single version byte namespace shared by every constructor that writes
+    /// one.
+    fn writes_version_byte(&self) -> bool {
+        self.format.is_some()
+    }
+
+    fn capacity(&self) -> usize {
+        let header = self.tag_overhead()
+            + if self.writes_version_byte() { 1 } else { 0 }
+            + if self.framed { FRAME_COUNT_SIZE } else { 0 };
+        self.max_size.saturating_sub(header)
+    }
+
+    fn push_unframed(&mut self, buf: &[u8]) -> Result<(), DatagramChunkerError> {
+        let capacity = self.capacity();
+
+        if buf.len() > capacity {
             return Err(DatagramChunkerError::ItemSizeTooBig);
         }
 
-        if self.current.len() + buf.len() > self.max_size {
-            self.datagrams.push(mem::take(&mut self.current));
+        if self.current.len() + buf.len() > capacity {
+            self.flush_current();
             self.current = buf.to_vec();
         } else {
             self.current.extend_from_slice(buf);
         }
 
+        self.message_index += 1;
+        Ok(())
+    }
+
+    fn push_framed(&mut self, buf: &[u8]) -> Result<(), DatagramChunkerError> {
+        let capacity = self.capacity();
+        let needed = FRAME_LENGTH_PREFIX_SIZE + buf.len();
+
+        // A framed datagram's per-message length prefix and message count are both
+        // `u16`, so a body over `u16::MAX` octets can't be length-prefixed, and a
+        // 65536th message in one datagram can't be counted without wrapping.
+        if buf.len() > u16::MAX as usize || needed > capacity {
+            return Err(DatagramChunkerError::ItemSizeTooBig);
+        }
+
+        if self.current_message_count == u16::MAX || self.current.len() + needed > capacity {
+            self.flush_current();
+        }
+
+        let mut prefix = OutOctetStream::new();
+        prefix.write_u16(buf.len() as u16)?;
+        self.current.extend_from_slice(prefix.octets_ref());
+        self.current.extend_from_slice(buf);
+        self.current_message_count += 1;
+
+        self.message_index += 1;
         Ok(())
     }
 
+    fn flush_current(&mut self) {
+        self.ranges.push(self.current_start_index..self.message_index);
+        self.current_start_index = self.message_index;
+
+        let mut header = OutOctetStream::new();
+        if let Some(tag) = self.tag {
+            header
+                .write_u16(tag)
+                .expect("writing to an in-memory stream cannot fail");
+        }
+
+        if let Some(format) = self.format {
+            header
+                .write_u8(format.version_byte())
+                .expect("writing to an in-memory stream cannot fail");
+        }
+
+        if self.framed {
+            header
+                .write_u16(self.current_message_count)
+                .expect("writing to an in-memory stream cannot fail");
+            self.current_message_count = 0;
+        }
+
+        if header.octets_ref().is_empty() {
+            self.datagrams.push(mem::take(&mut self.current));
+        } else {
+            let mut datagram = header.octets_ref().to_vec();
+            datagram.append(&mut self.current);
+            self.datagrams.push(datagram);
+        }
+    }
+
     pub fn finalize(mut self) -> Vec<Vec<u8>> {
-        if !self.current.is_empty() {
-            self.datagrams.push(self.current.clone());
+        if !self.current.is_empty() || self.current_message_count > 0 {
+            self.flush_current();
         }
         self.datagrams
     }
+
+    /// Like [`DatagramChunker::finalize`], but also returns which range of pushed
+    /// message indices ended up in each produced datagram, identified by a
+    /// monotonically increasing [`DatagramId`] in the order the datagrams were
+    /// produced. Feed the result to [`rechunk_unacked`] to retransmit the messages
+    /// behind any datagram that isn't acknowledged.
+    pub fn finalize_tracked(mut self) -> (Vec<Vec<u8>>, DatagramTracking) {
+        if !self.current.is_empty() || self.current_message_count > 0 {
+            self.flush_current();
+        }
+
+        let mode = self.mode();
+        let entries = self
+            .ranges
+            .into_iter()
+            .enumerate()
+            .map(|(index, range)| (DatagramId(index as u64), range))
+            .collect();
+
+        (self.datagrams, DatagramTracking { entries, mode })
+    }
+
+    fn mode(&self) -> ChunkerMode {
+        ChunkerMode {
+            framed: self.framed,
+            tag: self.tag,
+            format: self.format,
+        }
+    }
 }
 
 impl From<io::Error> for DatagramChunkerError {
@@ -116,14 +567,18 @@ pub fn serialize_to_datagrams<I, T>(
     max_datagram_size: usize,
 ) -> Result<Vec<Vec<u8>>, DatagramChunkerError>
 where
-    T: Serialize + Debug,
+    T: Serialize + Debug + SizeHint,
     I: AsRef<[T]>,
 {
     let mut chunker = DatagramChunker::new(max_datagram_size);
     for message in messages.as_ref() {
-        let mut temp = OutOctetStream::new();
-        message.serialize(&mut temp)?;
-        chunker.push(temp.octets_ref())?;
+        if let Some(hint) = message.serialized_size_hint() {
+            chunker.push_hinted(message, hint)?;
+        } else {
+            let mut temp = OutOctetStream::new();
+            message.serialize(&mut temp)?;
+            chunker.push(temp.octets_ref())?;
+        }
     }
 
     Ok(chunker.finalize())
@@ -186,3 +641,373 @@ where
     }
     Ok(all_messages)
 }
+
+/// Serializes a collection of messages into self-describing, framed datagrams.
+///
+/// Each datagram starts with a one octet format version and a `u16` message count,
+/// and every message body is preceded by a `u16` length prefix. This is the framed
+/// counterpart of [`serialize_to_datagrams`]; use [`deserialize_datagram_framed`] to
+/// read the result back.
+///
+/// # Arguments
+///
+/// * `messages` - The collection of messages to serialize.
+/// * `max_datagram_size` - The maximum size of each datagram in bytes, header included.
+///
+/// # Returns
+///
+/// A `Result` containing a vector of datagrams (`Vec<Vec<u8>>`) on success,
+/// or a `DatagramChunkerError` on failure.
+pub fn serialize_to_datagrams_framed<I, T>(
+    messages: I,
+    max_datagram_size: usize,
+) -> Result<Vec<Vec<u8>>, DatagramChunkerError>
+where
+    T: Serialize + Debug,
+    I: AsRef<[T]>,
+{
+    let mut chunker = DatagramChunker::new_framed(max_datagram_size);
+    for message in messages.as_ref() {
+        let mut temp = OutOctetStream::new();
+        message.serialize(&mut temp)?;
+        chunker.push(temp.octets_ref())?;
+    }
+
+    Ok(chunker.finalize())
+}
+
+/// Deserializes a single framed datagram (see [`serialize_to_datagrams_framed`]) into
+/// a vector of messages.
+///
+/// Unlike [`deserialize_datagram`], the message count and per-message length prefixes
+/// let each message be read from its own bounded sub-stream, so a deserialize failure
+/// on one message does not cascade into misreading the messages that follow it.
+///
+/// # Type Parameters
+///
+/// * `T` - The type of messages to deserialize, which must implement `Deserialize` and `Display`.
+///
+/// # Arguments
+///
+/// * `buf` - An octet slice representing the framed datagram to deserialize.
+///
+/// # Returns
+///
+/// A `Result` containing a vector of deserialized messages (`Vec<T>`) on success,
+/// or an `io::Error` on failure.
+pub fn deserialize_datagram_framed<T>(buf: &[u8]) -> Result<Vec<T>, io::Error>
+where
+    T: Deserialize + Display,
+{
+    let mut in_stream = InOctetStream::new(buf);
+
+    let version = in_stream.read_u8()?;
+    if version != DatagramFormat::V2.version_byte() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported framed datagram version {version}"),
+        ));
+    }
+
+    let message_count = in_stream.read_u16()?;
+    read_framed_messages(&mut in_stream, message_count)
+}
+
+/// Reads `message_count` length-prefixed messages from `in_stream`, isolating each
+/// one in its own bounded sub-stream. Shared by [`deserialize_datagram_framed`] and
+/// the `V2` arm of [`deserialize_datagram_versioned`].
+fn read_framed_messages<T>(
+    in_stream: &mut InOctetStream<'_>,
+    message_count: u16,
+) -> Result<Vec<T>, io::Error>
+where
+    T: Deserialize,
+{
+    let mut messages = Vec::with_capacity(message_count as usize);
+
+    for _ in 0..message_count {
+        let length = in_stream.read_u16()? as usize;
+        let mut message_octets = vec![0u8; length];
+        in_stream.read(&mut message_octets)?;
+
+        let mut message_stream = InOctetStream::new(&message_octets);
+        messages.push(T::deserialize(&mut message_stream)?);
+    }
+
+    Ok(messages)
+}
+
+/// Serializes a collection of messages into tagged datagrams, grouping messages by a
+/// `u16` channel tag so several logical streams can share one transport.
+///
+/// Messages are grouped by `tag_fn`, preserving the order in which each tag was first
+/// seen, and each group is chunked independently (see [`DatagramChunker::new_with_tag`]).
+/// Use [`deserialize_datagram_tagged`] to read a produced datagram back along with its tag.
+///
+/// # Arguments
+///
+/// * `messages` - The collection of messages to serialize.
+/// * `max_datagram_size` - The maximum size of each datagram in bytes, tag included.
+/// * `tag_fn` - Assigns a channel tag to a message.
+///
+/// # Returns
+///
+/// A `Result` containing a vector of tagged datagrams (`Vec<Vec<u8>>`) on success,
+/// or a `DatagramChunkerError` on failure.
+pub fn serialize_to_datagrams_tagged<I, T, F>(
+    messages: I,
+    max_datagram_size: usize,
+    tag_fn: F,
+) -> Result<Vec<Vec<u8>>, DatagramChunkerError>
+where
+    T: Serialize + Debug,
+    I: AsRef<[T]>,
+    F: Fn(&T) -> u16,
+{
+    let mut groups: Vec<(u16, Vec<&T>)> = Vec::new();
+    for message in messages.as_ref() {
+        let tag = tag_fn(message);
+        match groups.iter_mut().find(|(group_tag, _)| *group_tag == tag) {
+            Some((_, group)) => group.push(message),
+            None => groups.push((tag, vec![message])),
+        }
+    }
+
+    let mut all_datagrams = Vec::new();
+    for (tag, group) in groups {
+        let mut chunker = DatagramChunker::new_with_tag(max_datagram_size, tag);
+        for message in group {
+            let mut temp = OutOctetStream::new();
+            message.serialize(&mut temp)?;
+            chunker.push(temp.octets_ref())?;
+        }
+        all_datagrams.extend(chunker.finalize());
+    }
+
+    Ok(all_datagrams)
+}
+
+/// Deserializes a single tagged datagram (see [`serialize_to_datagrams_tagged`]) into
+/// its channel tag and the vector of messages it carried.
+///
+/// # Arguments
+///
+/// * `buf` - An octet slice representing the tagged datagram to deserialize.
+///
+/// # Returns
+///
+/// A `Result` containing the channel tag and deserialized messages on success,
+/// or an `io::Error` on failure.
+pub fn deserialize_datagram_tagged<T>(buf: &[u8]) -> Result<(u16, Vec<T>), io::Error>
+where
+    T: Deserialize + Display,
+{
+    let mut in_stream = InOctetStream::new(buf);
+    let tag = in_stream.read_u16()?;
+
+    let mut messages = vec![];
+    while !in_stream.has_reached_end() {
+        messages.push(T::deserialize(&mut in_stream)?);
+    }
+
+    Ok((tag, messages))
+}
+
+/// Re-serializes only the messages belonging to datagrams that haven't been
+/// acknowledged yet.
+///
+/// This turns [`DatagramChunker`] into a building block for a send queue: track each
+/// outgoing datagram's [`DatagramId`] (via [`DatagramChunker::finalize_tracked`]),
+/// record the ids the peer acknowledges, and periodically call this with everything
+/// still unacknowledged to get a fresh set of datagrams to send.
+///
+/// # Arguments
+///
+/// * `messages` - The original messages, in the same order used to produce `tracking`.
+/// * `tracking` - The result of a previous [`DatagramChunker::finalize_tracked`] call
+///   over `messages`. Its wire layout is reused for the re-sent datagrams, so they
+///   stay readable by whatever deserializer the original datagrams were meant for.
+/// * `acked` - The set of datagram ids the peer has confirmed receiving.
+/// * `max_datagram_size` - The maximum size of each re-sent datagram in bytes.
+///
+/// # Returns
+///
+/// A `Result` containing the re-chunked datagrams and their new tracking
+/// information, or a `DatagramChunkerError` on failure.
+pub fn rechunk_unacked<T>(
+    messages: &[T],
+    tracking: &DatagramTracking,
+    acked: &HashSet<DatagramId>,
+    max_datagram_size: usize,
+) -> Result<(Vec<Vec<u8>>, DatagramTracking), DatagramChunkerError>
+where
+    T: Serialize + Debug + SizeHint,
+{
+    let mut chunker = DatagramChunker::with_options(
+        max_datagram_size,
+        tracking.mode.framed,
+        tracking.mode.tag,
+        tracking.mode.format,
+    );
+
+    for (id, range) in tracking.entries.iter() {
+        if acked.contains(id) {
+            continue;
+        }
+
+        for message in &messages[range.clone()] {
+            if let Some(hint) = message.serialized_size_hint() {
+                chunker.push_hinted(message, hint)?;
+            } else {
+                let mut temp = OutOctetStream::new();
+                message.serialize(&mut temp)?;
+                chunker.push(temp.octets_ref())?;
+            }
+        }
+    }
+
+    Ok(chunker.finalize_tracked())
+}
+
+/// Deserializes a single datagram produced with [`DatagramChunker::new_with_format`],
+/// dispatching on its leading [`DatagramFormat`] version byte.
+///
+/// Unlike [`deserialize_datagram`] and [`deserialize_datagram_framed`], this rejects a
+/// datagram whose version byte isn't a known [`DatagramFormat`] with
+/// `DatagramChunkerError::UnsupportedFormat`, instead of misparsing it, so mixed-version
+/// deployments fail loudly.
+///
+/// # Arguments
+///
+/// * `buf` - An octet slice representing the versioned datagram to deserialize.
+///
+/// # Returns
+///
+/// A `Result` containing a vector of deserialized messages (`Vec<T>`) on success, or a
+/// `DatagramChunkerError` on failure.
+pub fn deserialize_datagram_versioned<T>(buf: &[u8]) -> Result<Vec<T>, DatagramChunkerError>
+where
+    T: Deserialize + Display,
+{
+    let mut in_stream = InOctetStream::new(buf);
+
+    let version_byte = in_stream.read_u8()?;
+    let format = DatagramFormat::from_version_byte(version_byte)
+        .ok_or(DatagramChunkerError::UnsupportedFormat(version_byte))?;
+
+    match format {
+        DatagramFormat::V1 => {
+            let mut messages = vec![];
+            while !in_stream.has_reached_end() {
+                messages.push(T::deserialize(&mut in_stream)?);
+            }
+            Ok(messages)
+        }
+        DatagramFormat::V2 => {
+            let message_count = in_stream.read_u16()?;
+            Ok(read_framed_messages(&mut in_stream, message_count)?)
+        }
+    }
+}
+
+/// Lazily deserializes the messages in a single framed datagram, yielding one `Result`
+/// per message instead of aborting the whole datagram on the first error.
+///
+/// Relies on the length prefixes written by [`serialize_to_datagrams_framed`] /
+/// [`DatagramChunker::new_framed`] to resynchronize after a malformed message: each
+/// `next()` call consumes exactly the length-prefixed octets it was told to, whether or
+/// not `T::deserialize` made sense of them, so a single bad message can be skipped with
+/// `.filter_map(Result::ok)` instead of poisoning the rest of the batch.
+pub struct DatagramDeserializer<'a, T> {
+    in_stream: InOctetStream<'a>,
+    remaining: u16,
+    phantom: PhantomData<T>,
+}
+
+impl<'a, T> DatagramDeserializer<'a, T>
+where
+    T: Deserialize,
+{
+    /// Creates a lazy, per-message iterator over a single framed datagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - An octet slice representing the framed datagram to deserialize.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the iterator on success, or an `io::Error` if the datagram's
+    /// header (format version and message count) can't be read.
+    pub fn new(buf: &'a [u8]) -> Result<Self, io::Error> {
+        let mut in_stream = InOctetStream::new(buf);
+        let version = in_stream.read_u8()?;
+        if version != DatagramFormat::V2.version_byte() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported framed datagram version {version}"),
+            ));
+        }
+        let remaining = in_stream.read_u16()?;
+
+        Ok(Self {
+            in_stream,
+            remaining,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<'a, T> Iterator for DatagramDeserializer<'a, T>
+where
+    T: Deserialize,
+{
+    type Item = Result<T, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let length = match self.in_stream.read_u16() {
+            Ok(length) => length as usize,
+            Err(err) => return Some(Err(err)),
+        };
+
+        let mut message_octets = vec![0u8; length];
+        if let Err(err) = self.in_stream.read(&mut message_octets) {
+            return Some(Err(err));
+        }
+
+        let mut message_stream = InOctetStream::new(&message_octets);
+        Some(T::deserialize(&mut message_stream))
+    }
+}
+
+/// Lazily deserializes messages across many framed datagrams, flattening each
+/// datagram's [`DatagramDeserializer`] into a single stream of results.
+///
+/// A malformed message - or an entire malformed datagram header - only produces an
+/// `Err` item in the stream; it does not stop messages from other datagrams, or later
+/// messages in the same datagram, from being read.
+///
+/// # Arguments
+///
+/// * `datagrams` - The framed datagrams to deserialize, in order.
+///
+/// # Returns
+///
+/// An iterator yielding one `Result<T, io::Error>` per message across all datagrams.
+pub fn deserialize_datagrams_framed_lazy<'a, T>(
+    datagrams: &'a [Vec<u8>],
+) -> impl Iterator<Item = Result<T, io::Error>> + 'a
+where
+    T: Deserialize + 'a,
+{
+    datagrams.iter().flat_map(|datagram| {
+        match DatagramDeserializer::new(datagram) {
+            Ok(iter) => Box::new(iter) as Box<dyn Iterator<Item = Result<T, io::Error>>>,
+            Err(err) => Box::new(std::iter::once(Err(err))),
+        }
+    })
+}
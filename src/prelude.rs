@@ -0,0 +1,14 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/datagram-chunker
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+//! Convenience re-export of the crate's public API.
+
+pub use crate::{
+    deserialize_datagram, deserialize_datagram_framed, deserialize_datagram_tagged,
+    deserialize_datagram_versioned, deserialize_datagrams, deserialize_datagrams_framed_lazy,
+    rechunk_unacked, serialize_to_datagrams, serialize_to_datagrams_framed,
+    serialize_to_datagrams_tagged, DatagramChunker, DatagramChunkerError, DatagramDeserializer,
+    DatagramFormat, DatagramId, SizeHint,
+};
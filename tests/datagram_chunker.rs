@@ -1,5 +1,6 @@
 use datagram_chunker::prelude::*;
 use flood_rs::prelude::*;
+use std::collections::HashSet;
 use std::fmt;
 use std::{
     fmt::{Display, Formatter},
@@ -35,6 +36,12 @@ impl Deserialize for TestMessage {
     }
 }
 
+impl SizeHint for TestMessage {
+    fn serialized_size_hint(&self) -> Option<usize> {
+        Some(4 + 2 + self.content.len())
+    }
+}
+
 impl Display for TestMessage {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
@@ -308,6 +315,588 @@ fn test_deserialize_datagrams_with_partial_empty_datagrams() {
     assert_eq!(deserialized_commands, expected);
 }
 
+#[test]
+fn test_serialize_and_deserialize_framed_basic() {
+    let messages = vec![
+        TestMessage {
+            id: 1,
+            content: "Hello".into(),
+        },
+        TestMessage {
+            id: 2,
+            content: "World".into(),
+        },
+    ];
+    let max_size = 1024;
+
+    let datagrams = serialize_to_datagrams_framed(&messages, max_size).unwrap();
+    assert_eq!(datagrams.len(), 1);
+
+    let deserialized: Vec<TestMessage> = deserialize_datagram_framed(&datagrams[0]).unwrap();
+    assert_eq!(deserialized, messages);
+}
+
+#[test]
+fn test_framed_datagram_readable_as_versioned_v2() {
+    // serialize_to_datagrams_framed / DatagramChunker::new_framed write the same wire
+    // layout as DatagramChunker::new_with_format(_, DatagramFormat::V2): both must be
+    // readable by either deserializer, sharing one version byte namespace.
+    let messages = vec![
+        TestMessage {
+            id: 1,
+            content: "Hello".into(),
+        },
+        TestMessage {
+            id: 2,
+            content: "World".into(),
+        },
+    ];
+
+    let datagrams = serialize_to_datagrams_framed(&messages, 1024).unwrap();
+
+    let via_framed: Vec<TestMessage> = deserialize_datagram_framed(&datagrams[0]).unwrap();
+    let via_versioned: Vec<TestMessage> = deserialize_datagram_versioned(&datagrams[0]).unwrap();
+    assert_eq!(via_framed, messages);
+    assert_eq!(via_versioned, messages);
+}
+
+#[test]
+fn test_serialize_framed_multiple_datagrams() {
+    let messages = vec![
+        TestMessage {
+            id: 1,
+            content: "A".repeat(500 - 6),
+        },
+        TestMessage {
+            id: 2,
+            content: "B".repeat(500 - 6),
+        },
+        TestMessage {
+            id: 3,
+            content: "C".repeat(500 - 6),
+        },
+    ];
+    let max_size = 1010; // room for the 3-octet frame header plus two 502-octet messages
+
+    let datagrams = serialize_to_datagrams_framed(&messages, max_size).unwrap();
+    assert_eq!(datagrams.len(), 2);
+
+    let deserialized1: Vec<TestMessage> = deserialize_datagram_framed(&datagrams[0]).unwrap();
+    let deserialized2: Vec<TestMessage> = deserialize_datagram_framed(&datagrams[1]).unwrap();
+    assert_eq!(deserialized1.len(), 2);
+    assert_eq!(deserialized2.len(), 1);
+    assert_eq!(deserialized1[0], messages[0]);
+    assert_eq!(deserialized1[1], messages[1]);
+    assert_eq!(deserialized2[0], messages[2]);
+}
+
+#[test]
+fn test_push_framed_rejects_message_body_over_u16_max() {
+    let mut chunker = DatagramChunker::new_framed(200_000);
+    let buf = vec![0u8; u16::MAX as usize + 1];
+
+    let result = chunker.push(&buf);
+    assert!(matches!(
+        result,
+        Err(DatagramChunkerError::ItemSizeTooBig)
+    ));
+}
+
+#[test]
+fn test_push_framed_flushes_instead_of_overflowing_message_count() {
+    // The framed message count is a `u16`; pushing more than 65535 messages into one
+    // unflushed datagram must flush early instead of wrapping the count.
+    let message_count = 70_000;
+    let max_size = 300_000; // room for 65535 one-byte messages plus their frame headers
+
+    let mut chunker = DatagramChunker::new_framed(max_size);
+    for _ in 0..message_count {
+        chunker.push(&[0u8]).unwrap();
+    }
+    let datagrams = chunker.finalize();
+
+    assert_eq!(datagrams.len(), 2);
+    let total_messages: usize = datagrams
+        .iter()
+        .map(|datagram| {
+            DatagramDeserializer::<UnhintedMessage>::new(datagram)
+                .unwrap()
+                .count()
+        })
+        .sum();
+    assert_eq!(total_messages, message_count);
+}
+
+#[test]
+fn test_deserialize_framed_isolates_malformed_message() {
+    // A framed datagram declaring two messages, but the first message's length
+    // prefix only covers part of a valid TestMessage: that first message should
+    // fail cleanly inside its own bounded sub-stream instead of the parser reading
+    // across the boundary into the second message.
+    let good_message = TestMessage {
+        id: 7,
+        content: "ok".into(),
+    };
+    let mut good_octets = OutOctetStream::new();
+    good_message.serialize(&mut good_octets).unwrap();
+
+    let mut datagram = vec![2u8]; // format version
+    datagram.extend_from_slice(&2u16.to_be_bytes()); // message count
+    datagram.extend_from_slice(&3u16.to_be_bytes()); // truncated length prefix
+    datagram.extend_from_slice(&good_octets.octets_ref()[..3]);
+    datagram.extend_from_slice(&(good_octets.octets_ref().len() as u16).to_be_bytes());
+    datagram.extend_from_slice(good_octets.octets_ref());
+
+    let result: Result<Vec<TestMessage>, io::Error> = deserialize_datagram_framed(&datagram);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_deserialize_framed_rejects_unknown_version() {
+    let mut datagram = vec![255u8]; // unsupported format version
+    datagram.extend_from_slice(&0u16.to_be_bytes());
+
+    let result: Result<Vec<TestMessage>, io::Error> = deserialize_datagram_framed(&datagram);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_serialize_and_deserialize_tagged_basic() {
+    let messages = vec![
+        TestMessage {
+            id: 1,
+            content: "control".into(),
+        },
+        TestMessage {
+            id: 2,
+            content: "bulk".into(),
+        },
+        TestMessage {
+            id: 3,
+            content: "more control".into(),
+        },
+    ];
+
+    let datagrams =
+        serialize_to_datagrams_tagged(&messages, 1024, |m| if m.id == 2 { 1 } else { 0 })
+            .unwrap();
+    assert_eq!(datagrams.len(), 2); // one datagram per distinct tag
+
+    let (tag0, messages0) = deserialize_datagram_tagged::<TestMessage>(&datagrams[0]).unwrap();
+    let (tag1, messages1) = deserialize_datagram_tagged::<TestMessage>(&datagrams[1]).unwrap();
+
+    assert_eq!(tag0, 0);
+    assert_eq!(
+        messages0,
+        vec![
+            TestMessage {
+                id: 1,
+                content: "control".into(),
+            },
+            TestMessage {
+                id: 3,
+                content: "more control".into(),
+            },
+        ]
+    );
+    assert_eq!(tag1, 1);
+    assert_eq!(
+        messages1,
+        vec![TestMessage {
+            id: 2,
+            content: "bulk".into(),
+        }]
+    );
+}
+
+#[test]
+fn test_serialize_to_datagrams_tagged_empty() {
+    let messages: Vec<TestMessage> = vec![];
+    let datagrams = serialize_to_datagrams_tagged(&messages, 1024, |_| 0).unwrap();
+    assert!(datagrams.is_empty());
+}
+
+#[test]
+fn test_serialize_and_deserialize_versioned_v1() {
+    let messages = vec![
+        TestMessage {
+            id: 1,
+            content: "Hello".into(),
+        },
+        TestMessage {
+            id: 2,
+            content: "World".into(),
+        },
+    ];
+
+    let mut chunker = DatagramChunker::new_with_format(1024, DatagramFormat::V1);
+    for msg in &messages {
+        chunker.push(&serialize_message(msg)).unwrap();
+    }
+    let datagrams = chunker.finalize();
+    assert_eq!(datagrams.len(), 1);
+
+    let deserialized: Vec<TestMessage> = deserialize_datagram_versioned(&datagrams[0]).unwrap();
+    assert_eq!(deserialized, messages);
+}
+
+#[test]
+fn test_serialize_and_deserialize_versioned_v2() {
+    let messages = vec![
+        TestMessage {
+            id: 1,
+            content: "Hello".into(),
+        },
+        TestMessage {
+            id: 2,
+            content: "World".into(),
+        },
+    ];
+
+    let mut chunker = DatagramChunker::new_with_format(1024, DatagramFormat::V2);
+    for msg in &messages {
+        chunker.push(&serialize_message(msg)).unwrap();
+    }
+    let datagrams = chunker.finalize();
+    assert_eq!(datagrams.len(), 1);
+
+    let deserialized: Vec<TestMessage> = deserialize_datagram_versioned(&datagrams[0]).unwrap();
+    assert_eq!(deserialized, messages);
+}
+
+#[test]
+fn test_deserialize_versioned_rejects_unknown_format() {
+    let datagram = vec![255u8]; // unrecognized format version byte
+
+    let result: Result<Vec<TestMessage>, DatagramChunkerError> =
+        deserialize_datagram_versioned(&datagram);
+
+    match result.err().unwrap() {
+        DatagramChunkerError::UnsupportedFormat(255) => (),
+        other => panic!("Expected UnsupportedFormat(255) error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_datagram_deserializer_yields_all_messages() {
+    let messages = vec![
+        TestMessage {
+            id: 1,
+            content: "Hello".into(),
+        },
+        TestMessage {
+            id: 2,
+            content: "World".into(),
+        },
+    ];
+    let datagrams = serialize_to_datagrams_framed(&messages, 1024).unwrap();
+
+    let deserialized: Vec<TestMessage> = DatagramDeserializer::new(&datagrams[0])
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(deserialized, messages);
+}
+
+#[test]
+fn test_datagram_deserializer_skips_malformed_message_and_continues() {
+    // A framed datagram with three messages, where the middle one has been replaced
+    // with garbage octets of the same length. Its length prefix still lets the
+    // iterator recover and read the third message.
+    let good1 = TestMessage {
+        id: 1,
+        content: "first".into(),
+    };
+    let good2 = TestMessage {
+        id: 3,
+        content: "third".into(),
+    };
+    let mut good1_octets = OutOctetStream::new();
+    good1.serialize(&mut good1_octets).unwrap();
+    let mut good2_octets = OutOctetStream::new();
+    good2.serialize(&mut good2_octets).unwrap();
+
+    let garbage = vec![0xffu8; good1_octets.octets_ref().len()];
+
+    let mut datagram = vec![2u8]; // format version
+    datagram.extend_from_slice(&3u16.to_be_bytes()); // message count
+    datagram.extend_from_slice(&(good1_octets.octets_ref().len() as u16).to_be_bytes());
+    datagram.extend_from_slice(good1_octets.octets_ref());
+    datagram.extend_from_slice(&(garbage.len() as u16).to_be_bytes());
+    datagram.extend_from_slice(&garbage);
+    datagram.extend_from_slice(&(good2_octets.octets_ref().len() as u16).to_be_bytes());
+    datagram.extend_from_slice(good2_octets.octets_ref());
+
+    let results: Vec<Result<TestMessage, io::Error>> =
+        DatagramDeserializer::new(&datagram).unwrap().collect();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap(), &good1);
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap(), &good2);
+
+    let recovered: Vec<TestMessage> = results.into_iter().filter_map(Result::ok).collect();
+    assert_eq!(recovered, vec![good1, good2]);
+}
+
+#[test]
+fn test_deserialize_datagrams_framed_lazy_flattens_across_datagrams() {
+    let messages1 = vec![TestMessage {
+        id: 1,
+        content: "first".into(),
+    }];
+    let messages2 = vec![TestMessage {
+        id: 2,
+        content: "second".into(),
+    }];
+
+    let mut datagrams = serialize_to_datagrams_framed(&messages1, 1024).unwrap();
+    datagrams.extend(serialize_to_datagrams_framed(&messages2, 1024).unwrap());
+    datagrams.push(vec![255u8]); // malformed trailing datagram: unsupported version
+
+    let results: Vec<Result<TestMessage, io::Error>> =
+        deserialize_datagrams_framed_lazy(&datagrams).collect();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap().id, 1);
+    assert_eq!(results[1].as_ref().unwrap().id, 2);
+    assert!(results[2].is_err());
+}
+
+#[test]
+fn test_finalize_tracked_maps_datagrams_to_message_ranges() {
+    let messages = vec![
+        TestMessage {
+            id: 1,
+            content: "A".repeat(500 - 6),
+        },
+        TestMessage {
+            id: 2,
+            content: "B".repeat(500 - 6),
+        },
+        TestMessage {
+            id: 3,
+            content: "C".repeat(500 - 6),
+        },
+    ];
+
+    let mut chunker = DatagramChunker::new(1000);
+    for msg in &messages {
+        chunker.push(&serialize_message(msg)).unwrap();
+    }
+    let (datagrams, tracking) = chunker.finalize_tracked();
+
+    assert_eq!(datagrams.len(), 2);
+    assert_eq!(tracking.len(), 2);
+    assert_eq!(tracking[0].0.value(), 0);
+    assert_eq!(tracking[0].1, 0..2);
+    assert_eq!(tracking[1].0.value(), 1);
+    assert_eq!(tracking[1].1, 2..3);
+}
+
+#[test]
+fn test_rechunk_unacked_only_resends_missing_datagrams() {
+    let messages = vec![
+        TestMessage {
+            id: 1,
+            content: "A".repeat(500 - 6),
+        },
+        TestMessage {
+            id: 2,
+            content: "B".repeat(500 - 6),
+        },
+        TestMessage {
+            id: 3,
+            content: "C".repeat(500 - 6),
+        },
+    ];
+
+    let (datagrams, tracking) = {
+        let mut chunker = DatagramChunker::new(1000);
+        for msg in &messages {
+            chunker.push(&serialize_message(msg)).unwrap();
+        }
+        chunker.finalize_tracked()
+    };
+    assert_eq!(datagrams.len(), 2);
+
+    let mut acked = HashSet::new();
+    acked.insert(tracking[0].0);
+
+    let (resent, resent_tracking) =
+        rechunk_unacked(&messages, &tracking, &acked, 1000).unwrap();
+
+    assert_eq!(resent.len(), 1);
+    let resent_messages: Vec<TestMessage> = deserialize_datagram(&resent[0]).unwrap();
+    assert_eq!(
+        resent_messages,
+        vec![TestMessage {
+            id: 3,
+            content: "C".repeat(500 - 6),
+        }]
+    );
+    assert_eq!(resent_tracking.len(), 1);
+}
+
+#[test]
+fn test_rechunk_unacked_preserves_framed_mode() {
+    let messages = vec![
+        TestMessage {
+            id: 1,
+            content: "A".into(),
+        },
+        TestMessage {
+            id: 2,
+            content: "B".into(),
+        },
+    ];
+
+    let (datagrams, tracking) = {
+        let mut chunker = DatagramChunker::new_framed(1024);
+        for msg in &messages {
+            chunker.push(&serialize_message(msg)).unwrap();
+        }
+        chunker.finalize_tracked()
+    };
+    assert_eq!(datagrams.len(), 1);
+
+    let acked = HashSet::new(); // nothing acked, resend everything
+    let (resent, _) = rechunk_unacked(&messages, &tracking, &acked, 1024).unwrap();
+
+    // The resend must still be framed, or a peer using the framed parser for the
+    // original stream will reject or misparse the retransmit.
+    let deserialized: Vec<TestMessage> = deserialize_datagram_framed(&resent[0]).unwrap();
+    assert_eq!(deserialized, messages);
+}
+
+/// A message type that opts out of providing a size hint, to exercise the fallback
+/// path in `serialize_to_datagrams`.
+#[derive(Debug, PartialEq)]
+struct UnhintedMessage {
+    value: u8,
+}
+
+impl Serialize for UnhintedMessage {
+    fn serialize(&self, stream: &mut impl flood_rs::WriteOctetStream) -> io::Result<()> {
+        stream.write_u8(self.value)
+    }
+}
+
+impl Deserialize for UnhintedMessage {
+    fn deserialize(stream: &mut impl flood_rs::ReadOctetStream) -> io::Result<Self> {
+        Ok(Self {
+            value: stream.read_u8()?,
+        })
+    }
+}
+
+impl Display for UnhintedMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "UnhintedMessage {{ value: {} }}", self.value)
+    }
+}
+
+impl SizeHint for UnhintedMessage {}
+
+#[test]
+fn test_serialize_to_datagrams_without_hint_falls_back() {
+    let messages = vec![UnhintedMessage { value: 1 }, UnhintedMessage { value: 2 }];
+
+    let datagrams = serialize_to_datagrams(&messages, 1024).unwrap();
+    assert_eq!(datagrams.len(), 1);
+
+    let deserialized: Vec<UnhintedMessage> = deserialize_datagram(&datagrams[0]).unwrap();
+    assert_eq!(deserialized, messages);
+}
+
+/// Same wire layout as `TestMessage`, but without a `serialized_size_hint` override,
+/// so it exercises the unhinted fallback path in `serialize_to_datagrams`.
+#[derive(Debug)]
+struct NoHintTestMessage {
+    id: u32,
+    content: String,
+}
+
+impl Serialize for NoHintTestMessage {
+    fn serialize(&self, stream: &mut impl flood_rs::WriteOctetStream) -> io::Result<()> {
+        stream.write_u32(self.id)?;
+        let string_octets = &self.content.clone().into_bytes();
+        stream.write_u16(string_octets.len() as u16)?;
+        stream.write(string_octets)
+    }
+}
+
+impl SizeHint for NoHintTestMessage {}
+
+#[test]
+fn test_serialize_to_datagrams_with_hint_matches_unhinted_output() {
+    let hinted_messages = vec![
+        TestMessage {
+            id: 1,
+            content: "Hello".into(),
+        },
+        TestMessage {
+            id: 2,
+            content: "World".into(),
+        },
+    ];
+    let unhinted_messages = vec![
+        NoHintTestMessage {
+            id: 1,
+            content: "Hello".into(),
+        },
+        NoHintTestMessage {
+            id: 2,
+            content: "World".into(),
+        },
+    ];
+
+    let hinted = serialize_to_datagrams(&hinted_messages, 1024).unwrap();
+    let unhinted = serialize_to_datagrams(&unhinted_messages, 1024).unwrap();
+
+    assert_eq!(hinted, unhinted);
+}
+
+/// A message whose `serialize` writes some octets and then fails, used to verify
+/// that `push_hinted` rolls back any partial bytes it wrote into the chunker's
+/// current datagram when serialization errors out partway through.
+struct FailingMessage;
+
+impl Serialize for FailingMessage {
+    fn serialize(&self, stream: &mut impl flood_rs::WriteOctetStream) -> io::Result<()> {
+        stream.write_u32(0xDEAD_BEEF)?;
+        Err(io::Error::other("simulated failure"))
+    }
+}
+
+#[test]
+fn test_push_hinted_rolls_back_on_serialize_error() {
+    let ok1 = TestMessage {
+        id: 1,
+        content: "ok1".into(),
+    };
+    let ok2 = TestMessage {
+        id: 2,
+        content: "ok2".into(),
+    };
+
+    let mut chunker = DatagramChunker::new_framed(1024);
+    chunker
+        .push_hinted(&ok1, ok1.serialized_size_hint().unwrap())
+        .unwrap();
+
+    let result = chunker.push_hinted(&FailingMessage, 4);
+    assert!(result.is_err());
+
+    chunker
+        .push_hinted(&ok2, ok2.serialized_size_hint().unwrap())
+        .unwrap();
+
+    let datagrams = chunker.finalize();
+    assert_eq!(datagrams.len(), 1);
+
+    let deserialized: Vec<TestMessage> = deserialize_datagram_framed(&datagrams[0]).unwrap();
+    assert_eq!(deserialized, vec![ok1, ok2]);
+}
+
 /// Helper function to create a serialized byte vector from a TestMessage.
 fn serialize_message(message: &TestMessage) -> Vec<u8> {
     let mut out_stream = OutOctetStream::new();